@@ -1,7 +1,12 @@
-use std::ops::*;
-use std::fmt;
-use std::fmt::Write;
-use std::cmp::Ordering;
+// The wrapper is `core`-only so the kernel can run on `no_std` embedded targets.
+// With the default (std / hardware-FP) build the arithmetic lowers to the
+// fast-math `core::intrinsics`; with the `soft-float` feature it falls back to
+// the plain operators and `libm`, which on targets like `thumbv7m` are provided
+// by compiler-builtins (`__adddf3`, `__muldf3`, `__divdf3`, `sqrt`, …).
+use core::ops::*;
+use core::fmt;
+use core::fmt::Write;
+use core::cmp::Ordering;
 
 #[allow(non_camel_case_types)]
 #[derive(Copy, Clone, PartialEq, PartialOrd)]
@@ -14,35 +19,53 @@ pub struct fff(pub f64);
 impl fff {
     #[inline(always)]
     pub fn powf<V: Into<f64>>(self, v: V) -> Self {
-        fff(self.0.powf(v.into()))
+        #[cfg(not(feature = "soft-float"))]
+        { fff(self.0.powf(v.into())) }
+        #[cfg(feature = "soft-float")]
+        { fff(libm::pow(self.0, v.into())) }
     }
 
     #[inline(always)]
     pub fn powi(self, v: i32) -> Self {
-        fff(self.0.powi(v))
+        #[cfg(not(feature = "soft-float"))]
+        { fff(self.0.powi(v)) }
+        #[cfg(feature = "soft-float")]
+        { fff(libm::pow(self.0, v as f64)) }
     }
 
     #[inline(always)]
     pub fn sqrt(self) -> Self {
-        fff(unsafe{std::intrinsics::sqrtf64(self.0)})
+        #[cfg(not(feature = "soft-float"))]
+        { fff(unsafe{core::intrinsics::sqrtf64(self.0)}) }
+        #[cfg(feature = "soft-float")]
+        { fff(libm::sqrt(self.0)) }
     }
 
     #[inline(always)]
     /// Very slow. Use trunc()
     pub fn round(self) -> Self {
-        self.0.round().into()
+        #[cfg(not(feature = "soft-float"))]
+        { self.0.round().into() }
+        #[cfg(feature = "soft-float")]
+        { fff(libm::round(self.0)) }
     }
 
     #[inline(always)]
     /// Very slow. Use trunc()
     pub fn floor(self) -> Self {
-        self.0.floor().into()
+        #[cfg(not(feature = "soft-float"))]
+        { self.0.floor().into() }
+        #[cfg(feature = "soft-float")]
+        { fff(libm::floor(self.0)) }
     }
 
     #[inline(always)]
     /// Very slow. Use trunc()
     pub fn ceil(self) -> Self {
-        self.0.ceil().into()
+        #[cfg(not(feature = "soft-float"))]
+        { self.0.ceil().into() }
+        #[cfg(feature = "soft-float")]
+        { fff(libm::ceil(self.0)) }
     }
 
     #[inline(always)]
@@ -53,20 +76,24 @@ impl fff {
 
     #[inline(always)]
     pub fn abs(self) -> Self {
-        self.0.abs().into()
+        #[cfg(not(feature = "soft-float"))]
+        { self.0.abs().into() }
+        #[cfg(feature = "soft-float")]
+        { fff(libm::fabs(self.0)) }
     }
 }
 
 macro_rules! impl_fast {
-    ($tr:ident, $fn:ident, $func:ident) => {
+    ($tr:ident, $fn:ident, $func:ident, $op:tt) => {
         impl $tr for fff {
             type Output = fff;
 
             #[inline(always)]
             fn $fn(self, other: fff) -> Self::Output {
-                unsafe {
-                    fff(std::intrinsics::$func(self.0, other.0))
-                }
+                #[cfg(not(feature = "soft-float"))]
+                unsafe { return fff(core::intrinsics::$func(self.0, other.0)); }
+                #[cfg(feature = "soft-float")]
+                { fff(self.0 $op other.0) }
             }
         }
 
@@ -75,9 +102,10 @@ macro_rules! impl_fast {
 
             #[inline(always)]
             fn $fn(self, other: f64) -> Self::Output {
-                unsafe {
-                    std::intrinsics::$func(self.0, other).into()
-                }
+                #[cfg(not(feature = "soft-float"))]
+                unsafe { return core::intrinsics::$func(self.0, other).into(); }
+                #[cfg(feature = "soft-float")]
+                { (self.0 $op other).into() }
             }
         }
 
@@ -86,9 +114,10 @@ macro_rules! impl_fast {
 
             #[inline(always)]
             fn $fn(self, other: fff) -> Self::Output {
-                unsafe {
-                    std::intrinsics::$func(self, other.0).into()
-                }
+                #[cfg(not(feature = "soft-float"))]
+                unsafe { return core::intrinsics::$func(self, other.0).into(); }
+                #[cfg(feature = "soft-float")]
+                { (self $op other.0).into() }
             }
         }
     }
@@ -105,13 +134,13 @@ macro_rules! impl_assign {
     }
 }
 
-impl_fast! {Add, add, fadd_fast}
+impl_fast! {Add, add, fadd_fast, +}
 impl_assign! {AddAssign, add, add_assign}
-impl_fast! {Sub, sub, fsub_fast}
+impl_fast! {Sub, sub, fsub_fast, -}
 impl_assign! {SubAssign, sub, sub_assign}
-impl_fast! {Mul, mul, fmul_fast}
-impl_fast! {Rem, rem, frem_fast}
-impl_fast! {Div, div, fdiv_fast}
+impl_fast! {Mul, mul, fmul_fast, *}
+impl_fast! {Rem, rem, frem_fast, %}
+impl_fast! {Div, div, fdiv_fast, /}
 
 impl Neg for fff {
     type Output = fff;